@@ -64,19 +64,180 @@ mod snap_unit_tests {
     }
 }
 
+/// A streaming, fixed-memory histogram of recorded durations.
+///
+/// Rather than keeping every sample around and sorting on demand, this records
+/// each duration (as an integer number of microseconds) into one of a fixed set
+/// of logarithmically-spaced buckets, the same trick the HdrHistogram used by the
+/// influx/dipstick ecosystems relies on. Each bucket covers a range that grows
+/// with the magnitude of the value, so a configurable number of significant
+/// figures is preserved across the whole tracked range with constant memory.
+///
+/// Recording is O(1) and allocation-free, and a quantile query is O(buckets),
+/// which keeps per-frame profiling cheap even after millions of samples.
+pub struct Histogram {
+    /// Largest value the histogram will track; bigger samples are clamped.
+    highest_trackable_value: u64,
+    unit_magnitude: u32,
+    sub_bucket_half_count_magnitude: u32,
+    sub_bucket_half_count: u32,
+    sub_bucket_mask: u64,
+    counts: Vec<u64>,
+    total_count: u64,
+    min: u64,
+    max: u64,
+}
+
+impl Histogram {
+    fn new(significant_figures: u8, highest_trackable_value: u64) -> Self {
+        // The resolution at the bottom of the range: the number of distinct
+        // values we want to tell apart with single-unit precision.
+        let largest_single_unit = 2 * 10u64.pow(significant_figures as u32);
+        let sub_bucket_count_magnitude =
+            (largest_single_unit as f64).log2().ceil() as u32;
+        let sub_bucket_half_count_magnitude = sub_bucket_count_magnitude.max(1) - 1;
+        let sub_bucket_count = 1u32 << (sub_bucket_half_count_magnitude + 1);
+        let sub_bucket_half_count = sub_bucket_count / 2;
+        let unit_magnitude = 0;
+        let sub_bucket_mask = ((sub_bucket_count as u64) - 1) << unit_magnitude;
+
+        // Walk upwards in powers of two until the top bucket covers the
+        // highest trackable value, so we know how many buckets to allocate.
+        let mut smallest_untrackable = (sub_bucket_count as u64) << unit_magnitude;
+        let mut bucket_count = 1u32;
+        while smallest_untrackable < highest_trackable_value {
+            smallest_untrackable <<= 1;
+            bucket_count += 1;
+        }
+        let counts_len = ((bucket_count + 1) * sub_bucket_half_count) as usize;
+
+        Histogram {
+            highest_trackable_value,
+            unit_magnitude,
+            sub_bucket_half_count_magnitude,
+            sub_bucket_half_count,
+            sub_bucket_mask,
+            counts: vec![0; counts_len],
+            total_count: 0,
+            min: u64::MAX,
+            max: 0,
+        }
+    }
+
+    fn record(&mut self, value: u64) {
+        let value = value.min(self.highest_trackable_value);
+        let index = self.counts_index_for(value);
+        self.counts[index] += 1;
+        self.total_count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    fn clear(&mut self) {
+        for count in self.counts.iter_mut() {
+            *count = 0;
+        }
+        self.total_count = 0;
+        self.min = u64::MAX;
+        self.max = 0;
+    }
+
+    fn bucket_index(&self, value: u64) -> u32 {
+        let pow2ceiling = 64 - (value | self.sub_bucket_mask).leading_zeros();
+        pow2ceiling - self.unit_magnitude - (self.sub_bucket_half_count_magnitude + 1)
+    }
+
+    fn sub_bucket_index(&self, value: u64, bucket_index: u32) -> u32 {
+        (value >> (bucket_index + self.unit_magnitude)) as u32
+    }
+
+    fn counts_index(&self, bucket_index: u32, sub_bucket_index: u32) -> usize {
+        let bucket_base = (bucket_index + 1) << self.sub_bucket_half_count_magnitude;
+        let offset = sub_bucket_index as i64 - self.sub_bucket_half_count as i64;
+        (bucket_base as i64 + offset) as usize
+    }
+
+    fn counts_index_for(&self, value: u64) -> usize {
+        let bucket_index = self.bucket_index(value);
+        let sub_bucket_index = self.sub_bucket_index(value, bucket_index);
+        self.counts_index(bucket_index, sub_bucket_index)
+    }
+
+    /// The representative (lower-bound) value stored at a given counts index.
+    fn value_at_index(&self, index: usize) -> u64 {
+        let mut bucket_index = (index >> self.sub_bucket_half_count_magnitude) as i64 - 1;
+        let mut sub_bucket_index = ((index & (self.sub_bucket_half_count as usize - 1))
+            + self.sub_bucket_half_count as usize) as i64;
+        if bucket_index < 0 {
+            sub_bucket_index -= self.sub_bucket_half_count as i64;
+            bucket_index = 0;
+        }
+        (sub_bucket_index as u64) << (bucket_index as u32 + self.unit_magnitude)
+    }
+
+    /// The lower bound of the bucket in which the cumulative count first
+    /// reaches `quantile` of all recorded samples.
+    fn value_at_quantile(&self, quantile: f32) -> u64 {
+        if self.total_count == 0 {
+            return 0;
+        }
+        let quantile = quantile.clamp(0.0, 1.0);
+        // Round up so that e.g. p100 lands on the last populated bucket.
+        let target = ((quantile as f64) * self.total_count as f64).ceil() as u64;
+        let target = target.max(1);
+        let mut cumulative = 0u64;
+        for index in 0..self.counts.len() {
+            cumulative += self.counts[index];
+            if cumulative >= target {
+                return self.value_at_index(index);
+            }
+        }
+        self.max
+    }
+}
+
+/// A statistical summary of the durations recorded by a [`Monitor`].
+///
+/// All durations are expressed in seconds to match [`Snap::duration`].
+#[derive(Debug, Clone)]
+pub struct Summary {
+    pub count: u64,
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    pub stddev: f32,
+    pub p50: f32,
+    pub p90: f32,
+    pub p99: f32,
+    pub p999: f32,
+}
+
+/// Converts an integer number of microseconds back into seconds.
+fn us_to_secs(us: u64) -> f32 {
+    us as f32 / 1_000_000.0
+}
+
+/// Converts a duration in seconds into an integer number of microseconds.
+fn secs_to_us(secs: f32) -> u64 {
+    (secs * 1_000_000.0).round().max(0.0) as u64
+}
+
 /// Represents a monitor for a section of code.
-/// 
+///
 /// Monitors can be used to track the performance of a section of code.
-/// 
+///
 /// # Example
-/// 
+///
 pub struct Monitor {
     pub name: String,
     pub snaps: RwLock<Vec<Snap>>,
     /// To prevent multiple recordings at the same time being submitted,
     /// we use this flag to prevent it.
     pub create_new_snap: bool,
-    pub max_snapshots: usize
+    pub max_snapshots: usize,
+    /// Streaming histogram of every recorded duration, used for quantiles and
+    /// summary statistics without having to clone and sort the snapshots.
+    histogram: Histogram,
 }
 
 pub struct MonitorIterator<'a> {
@@ -123,29 +284,40 @@ impl<'a> MonitorRecordingGuard<'a> {
 
 impl<'a> Drop for MonitorRecordingGuard<'a> {
     fn drop(&mut self) {
-        let snaps = &mut self.monitor.snaps.write().unwrap();
-        match self.monitor.create_new_snap {
-            true => {
-                snaps.push(self.snap.clone());
-            },
-            false => {
-                let last = snaps.len() - 1;
-                let last_snap = &mut snaps[last];
-                last_snap.duration += self.snap.duration;
+        {
+            let snaps = &mut self.monitor.snaps.write().unwrap();
+            match self.monitor.create_new_snap {
+                true => {
+                    snaps.push(self.snap.clone());
+                },
+                false => {
+                    let last = snaps.len() - 1;
+                    let last_snap = &mut snaps[last];
+                    last_snap.duration += self.snap.duration;
+                }
             }
         }
+        self.monitor.histogram.record(secs_to_us(self.snap.duration));
     }
 }
 
 impl<'a> Monitor {
     const DEFAULT_MAX_SNAPSHOTS: usize = 100;
+    /// Significant figures preserved by the backing histogram.
+    const HISTOGRAM_SIGNIFICANT_FIGURES: u8 = 3;
+    /// Largest duration the histogram tracks, in microseconds (one hour).
+    const HISTOGRAM_HIGHEST_TRACKABLE_US: u64 = 3_600_000_000;
 
     pub fn new(name: &str) -> Self {
         Monitor {
             name: name.to_string(),
             snaps: RwLock::new(Vec::new()),
             create_new_snap: false,
-            max_snapshots: Self::DEFAULT_MAX_SNAPSHOTS
+            max_snapshots: Self::DEFAULT_MAX_SNAPSHOTS,
+            histogram: Histogram::new(
+                Self::HISTOGRAM_SIGNIFICANT_FIGURES,
+                Self::HISTOGRAM_HIGHEST_TRACKABLE_US,
+            ),
         }
     }
 
@@ -170,11 +342,58 @@ impl<'a> Monitor {
     }
 
     pub fn percentile(&self, percentile: f32) -> f32 {
-        let snaps = self.snaps.read().unwrap();
-        let mut sorted = snaps.clone();
-        sorted.sort_by(|a, b| a.duration.partial_cmp(&b.duration).unwrap());
-        let index = (percentile * snaps.len() as f32) as usize;
-        sorted[index].duration
+        us_to_secs(self.histogram.value_at_quantile(percentile))
+    }
+
+    /// Computes a statistical summary of every duration recorded so far.
+    ///
+    /// This walks the backing histogram once for the mean and standard
+    /// deviation and once per quantile, so it is O(buckets) and performs no
+    /// allocation or sorting regardless of how many samples were recorded.
+    pub fn summary(&self) -> Summary {
+        let hist = &self.histogram;
+        if hist.total_count == 0 {
+            return Summary {
+                count: 0,
+                min: 0.0,
+                max: 0.0,
+                mean: 0.0,
+                stddev: 0.0,
+                p50: 0.0,
+                p90: 0.0,
+                p99: 0.0,
+                p999: 0.0,
+            };
+        }
+
+        // Accumulate the first two moments from the bucket counts so the mean
+        // and standard deviation cost nothing beyond the single walk.
+        let mut sum = 0.0f64;
+        let mut sum_sq = 0.0f64;
+        for index in 0..hist.counts.len() {
+            let count = hist.counts[index];
+            if count == 0 {
+                continue;
+            }
+            let value = hist.value_at_index(index) as f64;
+            sum += value * count as f64;
+            sum_sq += value * value * count as f64;
+        }
+        let total = hist.total_count as f64;
+        let mean = sum / total;
+        let variance = (sum_sq / total - mean * mean).max(0.0);
+
+        Summary {
+            count: hist.total_count,
+            min: us_to_secs(hist.min),
+            max: us_to_secs(hist.max),
+            mean: us_to_secs(mean.round() as u64),
+            stddev: us_to_secs(variance.sqrt().round() as u64),
+            p50: us_to_secs(hist.value_at_quantile(0.50)),
+            p90: us_to_secs(hist.value_at_quantile(0.90)),
+            p99: us_to_secs(hist.value_at_quantile(0.99)),
+            p999: us_to_secs(hist.value_at_quantile(0.999)),
+        }
     }
 
     pub fn iter(&self) -> MonitorIterator{
@@ -208,6 +427,7 @@ impl<'a> Monitor {
     pub fn clear(&mut self) {
         let mut snaps = self.snaps.write().unwrap();
         snaps.clear();
+        self.histogram.clear();
     }
 
     pub fn new_frame(&mut self) {
@@ -217,8 +437,16 @@ impl<'a> Monitor {
 
 impl Display for Monitor {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let snaps = self.snaps.read().unwrap();
-        write!(f, "Monitor: ({} snaps, avg: {:.2}ms, 1%: {:.2}ms)", snaps.len(), self.average() * 1000.0, self.percentile(0.01) * 1000.0)
+        let summary = self.summary();
+        write!(
+            f,
+            "Monitor: ({} snaps, mean: {:.2}ms, p50: {:.2}ms, p99: {:.2}ms, p99.9: {:.2}ms)",
+            summary.count,
+            summary.mean * 1000.0,
+            summary.p50 * 1000.0,
+            summary.p99 * 1000.0,
+            summary.p999 * 1000.0
+        )
     }
 }
 
@@ -291,6 +519,33 @@ mod monitor_unit_tests {
         assert_eq!(monitor.average(), avg);
     }
 
+    #[test]
+    fn test_monitor_summary() {
+        let mut monitor = Monitor::new("test");
+        for _ in 0..8 {
+            {
+                let guard = monitor.record();
+                assert!(guard.is_some());
+            }
+            monitor.new_frame();
+        }
+        let summary = monitor.summary();
+        assert_eq!(summary.count, 8);
+        assert!(summary.max >= summary.min);
+        assert!(summary.p99 >= summary.p50);
+    }
+
+    #[test]
+    fn test_monitor_percentile_does_not_panic_at_one() {
+        let mut monitor = Monitor::new("test");
+        {
+            let guard = monitor.record();
+            assert!(guard.is_some());
+        }
+        // A percentile of 1.0 used to index past the end of the sorted vec.
+        let _ = monitor.percentile(1.0);
+    }
+
     #[test]
     fn test_monitor_clear() {
         let mut monitor = Monitor::new("test");